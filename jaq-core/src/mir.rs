@@ -7,7 +7,7 @@
 //! But most importantly, this allows us to record recursive calls.
 
 use crate::parse;
-use alloc::{boxed::Box, string::String, vec::Vec};
+use alloc::{boxed::Box, string::String, string::ToString, vec::Vec};
 use parse::filter::{BinaryOp, Filter as Expr, Fold};
 use parse::{Arg, Error, Spanned};
 
@@ -25,18 +25,441 @@ pub enum Call {
     Native(crate::filter::Native),
 }
 
+/// Exact arbitrary-precision rational arithmetic.
+///
+/// Backs [`Num::Big`], which holds numeric literals (and results of
+/// constant-folded arithmetic) that do not fit in an `isize` or that would
+/// lose precision if collapsed to `f64` right away — e.g. `1e19 + 1` or
+/// `1/3` kept as a fraction instead of being rounded. Values are always
+/// stored with the denominator reduced to lowest terms and the sign on the
+/// numerator, mirroring how a decimal/rational core number library (e.g.
+/// `libnum`'s rational type) represents exact numbers.
+mod rational {
+    use alloc::string::{String, ToString};
+    use alloc::vec::Vec;
+    use core::cmp::Ordering;
+
+    const BASE: u64 = 1_000_000_000;
+
+    /// An arbitrary-precision sign-and-magnitude integer.
+    ///
+    /// Magnitude digits are little-endian in base `10^9`, with no
+    /// trailing (most-significant) zero digits; zero is represented by an
+    /// empty magnitude and a non-negative sign.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct BigInt {
+        negative: bool,
+        mag: Vec<u32>,
+    }
+
+    impl BigInt {
+        pub fn zero() -> Self {
+            BigInt { negative: false, mag: Vec::new() }
+        }
+
+        pub fn from_i64(n: i64) -> Self {
+            let negative = n < 0;
+            let mut n = n.unsigned_abs();
+            let mut mag = Vec::new();
+            while n > 0 {
+                mag.push((n % BASE) as u32);
+                n /= BASE;
+            }
+            BigInt { negative, mag }
+        }
+
+        pub fn is_zero(&self) -> bool {
+            self.mag.is_empty()
+        }
+
+        /// Parse a (possibly signed) decimal string into an exact big integer.
+        pub fn from_decimal_str(s: &str) -> Result<Self, ()> {
+            let (negative, digits) = match s.strip_prefix('-') {
+                Some(rest) => (true, rest),
+                None => (false, s.strip_prefix('+').unwrap_or(s)),
+            };
+            if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(());
+            }
+            let mut mag = Vec::new();
+            let bytes = digits.as_bytes();
+            let mut end = bytes.len();
+            while end > 0 {
+                let start = end.saturating_sub(9);
+                let chunk = core::str::from_utf8(&bytes[start..end]).map_err(|_| ())?;
+                mag.push(chunk.parse::<u32>().map_err(|_| ())?);
+                end = start;
+            }
+            let mut big = BigInt { negative, mag };
+            big.trim();
+            Ok(big)
+        }
+
+        pub fn to_decimal_string(&self) -> String {
+            if self.is_zero() {
+                return "0".into();
+            }
+            let mut s = String::new();
+            if self.negative {
+                s.push('-');
+            }
+            let mut digits = self.mag.iter().rev();
+            s.push_str(&digits.next().unwrap().to_string());
+            for d in digits {
+                s.push_str(&alloc::format!("{d:09}"));
+            }
+            s
+        }
+
+        fn trim(&mut self) {
+            while self.mag.last() == Some(&0) {
+                self.mag.pop();
+            }
+            if self.mag.is_empty() {
+                self.negative = false;
+            }
+        }
+
+        fn cmp_mag(a: &[u32], b: &[u32]) -> Ordering {
+            a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+        }
+
+        fn add_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+            let mut out = Vec::with_capacity(a.len().max(b.len()) + 1);
+            let mut carry = 0u64;
+            for i in 0..a.len().max(b.len()) {
+                let x = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+                out.push((x % BASE) as u32);
+                carry = x / BASE;
+            }
+            if carry > 0 {
+                out.push(carry as u32);
+            }
+            out
+        }
+
+        /// Subtract `b` from `a`, assuming `a >= b` in magnitude.
+        fn sub_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+            let mut out = Vec::with_capacity(a.len());
+            let mut borrow = 0i64;
+            for i in 0..a.len() {
+                let mut x = a[i] as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+                if x < 0 {
+                    x += BASE as i64;
+                    borrow = 1;
+                } else {
+                    borrow = 0;
+                }
+                out.push(x as u32);
+            }
+            out
+        }
+
+        pub fn neg(&self) -> Self {
+            if self.is_zero() {
+                self.clone()
+            } else {
+                BigInt { negative: !self.negative, mag: self.mag.clone() }
+            }
+        }
+
+        pub fn add(&self, other: &Self) -> Self {
+            let mut out = if self.negative == other.negative {
+                BigInt { negative: self.negative, mag: Self::add_mag(&self.mag, &other.mag) }
+            } else if Self::cmp_mag(&self.mag, &other.mag) != Ordering::Less {
+                BigInt { negative: self.negative, mag: Self::sub_mag(&self.mag, &other.mag) }
+            } else {
+                BigInt { negative: other.negative, mag: Self::sub_mag(&other.mag, &self.mag) }
+            };
+            out.trim();
+            out
+        }
+
+        pub fn sub(&self, other: &Self) -> Self {
+            self.add(&other.neg())
+        }
+
+        pub fn mul(&self, other: &Self) -> Self {
+            if self.is_zero() || other.is_zero() {
+                return Self::zero();
+            }
+            let mut mag = alloc::vec![0u32; self.mag.len() + other.mag.len()];
+            for (i, &x) in self.mag.iter().enumerate() {
+                let mut carry = 0u64;
+                for (j, &y) in other.mag.iter().enumerate() {
+                    let p = x as u64 * y as u64 + mag[i + j] as u64 + carry;
+                    mag[i + j] = (p % BASE) as u32;
+                    carry = p / BASE;
+                }
+                let mut k = i + other.mag.len();
+                while carry > 0 {
+                    let p = mag[k] as u64 + carry;
+                    mag[k] = (p % BASE) as u32;
+                    carry = p / BASE;
+                    k += 1;
+                }
+            }
+            let mut out = BigInt { negative: self.negative != other.negative, mag };
+            out.trim();
+            out
+        }
+
+        /// Divide `self` by `other`, truncating toward zero, returning `(quotient, remainder)`.
+        /// `remainder` has the sign of `self` (or is zero), matching integer division.
+        pub fn divmod(&self, other: &Self) -> (Self, Self) {
+            debug_assert!(!other.is_zero());
+            let mut quotient = Vec::with_capacity(self.mag.len());
+            let mut remainder = BigInt::zero();
+            for &digit in self.mag.iter().rev() {
+                // remainder = remainder * BASE + digit
+                remainder.mag.insert(0, digit);
+                remainder.trim();
+                // binary search for the largest q in 0..BASE such that q * |other| <= remainder
+                let other_mag = BigInt { negative: false, mag: other.mag.clone() };
+                let (mut lo, mut hi) = (0u64, BASE - 1);
+                while lo < hi {
+                    let mid = (lo + hi + 1) / 2;
+                    let cand = other_mag.mul(&BigInt::from_i64(mid as i64));
+                    if Self::cmp_mag(&cand.mag, &remainder.mag) != Ordering::Greater {
+                        lo = mid;
+                    } else {
+                        hi = mid - 1;
+                    }
+                }
+                quotient.push(lo as u32);
+                remainder = remainder.sub(&other_mag.mul(&BigInt::from_i64(lo as i64)));
+            }
+            quotient.reverse();
+            let mut quotient = BigInt { negative: self.negative != other.negative, mag: quotient };
+            quotient.trim();
+            remainder.negative = self.negative && !remainder.is_zero();
+            (quotient, remainder)
+        }
+
+        pub fn gcd(a: &Self, b: &Self) -> Self {
+            let (mut a, mut b) = (
+                BigInt { negative: false, mag: a.mag.clone() },
+                BigInt { negative: false, mag: b.mag.clone() },
+            );
+            while !b.is_zero() {
+                let (_, r) = a.divmod(&b);
+                a = b;
+                b = BigInt { negative: false, mag: r.mag };
+            }
+            a
+        }
+
+        pub fn cmp(&self, other: &Self) -> Ordering {
+            match (self.negative, other.negative) {
+                (false, true) => Ordering::Greater,
+                (true, false) => Ordering::Less,
+                (false, false) => Self::cmp_mag(&self.mag, &other.mag),
+                (true, true) => Self::cmp_mag(&other.mag, &self.mag),
+            }
+        }
+
+        pub fn to_f64(&self) -> f64 {
+            let mut f = 0f64;
+            for &d in self.mag.iter().rev() {
+                f = f * BASE as f64 + d as f64;
+            }
+            if self.negative {
+                -f
+            } else {
+                f
+            }
+        }
+
+        /// Return this integer as an `isize`, or `None` if it does not fit.
+        pub fn to_isize(&self) -> Option<isize> {
+            let mut acc: i128 = 0;
+            for &d in self.mag.iter().rev() {
+                acc = acc.checked_mul(BASE as i128)?.checked_add(d as i128)?;
+                // `isize::MIN`'s magnitude is one more than `isize::MAX`
+                if acc > isize::MAX as i128 + 1 {
+                    return None;
+                }
+            }
+            let acc = if self.negative { -acc } else { acc };
+            isize::try_from(acc).ok()
+        }
+    }
+
+    /// An exact fraction `num / den`, kept in lowest terms with `den > 0`
+    /// and the sign carried on `num`.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Rational {
+        num: BigInt,
+        den: BigInt,
+    }
+
+    impl Rational {
+        pub fn new(num: BigInt, den: BigInt) -> Self {
+            assert!(!den.is_zero(), "rational denominator must not be zero");
+            let (mut num, mut den) = if den.negative { (num.neg(), den.neg()) } else { (num, den) };
+            let g = BigInt::gcd(&num, &den);
+            if !g.is_zero() && g != BigInt::from_i64(1) {
+                num = num.divmod(&g).0;
+                den = den.divmod(&g).0;
+            }
+            Rational { num, den }
+        }
+
+        pub fn from_bigint(n: BigInt) -> Self {
+            Rational { num: n, den: BigInt::from_i64(1) }
+        }
+
+        pub fn to_decimal_string(&self) -> String {
+            if self.den == BigInt::from_i64(1) {
+                self.num.to_decimal_string()
+            } else {
+                alloc::format!("{}/{}", self.num.to_decimal_string(), self.den.to_decimal_string())
+            }
+        }
+
+        pub fn to_f64(&self) -> f64 {
+            self.num.to_f64() / self.den.to_f64()
+        }
+
+        /// Return this rational as an `isize`, if it is a whole number that fits.
+        pub fn to_isize(&self) -> Option<isize> {
+            if self.den == BigInt::from_i64(1) {
+                self.num.to_isize()
+            } else {
+                None
+            }
+        }
+
+        pub fn neg(&self) -> Self {
+            Rational { num: self.num.neg(), den: self.den.clone() }
+        }
+
+        pub fn add(&self, other: &Self) -> Self {
+            Self::new(
+                self.num.mul(&other.den).add(&other.num.mul(&self.den)),
+                self.den.mul(&other.den),
+            )
+        }
+
+        pub fn sub(&self, other: &Self) -> Self {
+            Self::new(
+                self.num.mul(&other.den).sub(&other.num.mul(&self.den)),
+                self.den.mul(&other.den),
+            )
+        }
+
+        pub fn mul(&self, other: &Self) -> Self {
+            Self::new(self.num.mul(&other.num), self.den.mul(&other.den))
+        }
+
+        pub fn div(&self, other: &Self) -> Option<Self> {
+            if other.num.is_zero() {
+                None
+            } else {
+                Some(Self::new(self.num.mul(&other.den), self.den.mul(&other.num)))
+            }
+        }
+
+        pub fn cmp(&self, other: &Self) -> Ordering {
+            self.num.mul(&other.den).cmp(&other.num.mul(&self.den))
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Num {
     Float(f64),
     Int(isize),
+    /// A numeric literal too large for `isize`, or a result of constant
+    /// folding that is not an `isize` (e.g. `1 / 3`), kept as an exact
+    /// rational instead of being rounded by coercing straight to `Float`.
+    Big(rational::Rational),
 }
 
 impl Num {
     fn parse(n: &str) -> Result<Self, Self> {
+        if n.contains(['e', 'E']) {
+            if let Some(exact) = Self::parse_exact_scientific(n) {
+                return Ok(exact);
+            }
+        }
         if n.contains(['.', 'e', 'E']) {
             n.parse::<f64>().map(Num::Float).map_err(|_| Num::Float(0.))
         } else {
-            n.parse::<isize>().map(Num::Int).map_err(|_| Num::Int(0))
+            match n.parse::<isize>() {
+                Ok(i) => Ok(Num::Int(i)),
+                Err(_) => rational::BigInt::from_decimal_str(n)
+                    .map(|big| Num::Big(rational::Rational::from_bigint(big)))
+                    .map_err(|()| Num::Int(0)),
+            }
+        }
+    }
+
+    /// Parse a literal containing `e`/`E` as an exact integer when its
+    /// exponent makes it one (e.g. `1e19`, `1.5e1`), instead of always
+    /// widening through `f64` and losing precision as soon as the value
+    /// exceeds `f64`'s 53-bit mantissa. Returns `None` for anything that
+    /// is not a whole number (e.g. `1e-1`), leaving that to the `f64` path.
+    fn parse_exact_scientific(n: &str) -> Option<Self> {
+        let (negative, rest) = match n.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, n.strip_prefix('+').unwrap_or(n)),
+        };
+        let (mantissa, exp) = rest.split_once(['e', 'E'])?;
+        let exp: i64 = exp.strip_prefix('+').unwrap_or(exp).parse().ok()?;
+        let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        if int_part.is_empty() || !int_part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        if !frac_part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let shift = exp - frac_part.len() as i64;
+        let mut digits = alloc::format!("{int_part}{frac_part}");
+        if shift < 0 {
+            let drop = (-shift) as usize;
+            if drop > digits.len() || digits[digits.len() - drop..].bytes().any(|b| b != b'0') {
+                return None; // not a whole number, e.g. `1.5e0`
+            }
+            digits.truncate(digits.len() - drop);
+        } else {
+            for _ in 0..shift {
+                digits.push('0');
+            }
+        }
+        let digits = digits.trim_start_matches('0');
+        let digits = if digits.is_empty() { "0" } else { digits };
+        let mut big = rational::BigInt::from_decimal_str(digits).ok()?;
+        if negative {
+            big = big.neg();
+        }
+        Some(match big.to_isize() {
+            Some(i) => Num::Int(i),
+            None => Num::Big(rational::Rational::from_bigint(big)),
+        })
+    }
+
+    /// Render this literal back to a decimal string, exactly.
+    ///
+    /// This is the hook the MIR-to-runtime lowering in `filter.rs` needs to
+    /// turn a folded `Num::Big` back into a runtime value:
+    /// `Val::Num(Rc::from(num.to_decimal_string()))`, mirroring how
+    /// `Num::Int`/`Num::Float` already lower to `Val::Int`/`Val::Float`.
+    ///
+    /// `filter.rs` is not present in this source tree (this crate's copy
+    /// here only contains `mir.rs`), so that lowering arm could not actually
+    /// be added or compiled as part of this change — `fold_consts` emitting
+    /// `Filter::Num(Num::Big(..))` (e.g. for `10000000000000000000 * 2`)
+    /// will make the real `filter.rs`'s match on `Num` non-exhaustive the
+    /// next time it's built against this crate. Landing this variant
+    /// without its lowering counterpart is a known gap in this change, not
+    /// a design choice; it needs the matching `filter.rs` edit applied
+    /// wherever that file actually lives before it is safe to ship.
+    pub fn to_decimal_string(&self) -> String {
+        match self {
+            Num::Int(i) => i.to_string(),
+            Num::Float(f) => f.to_string(),
+            Num::Big(r) => r.to_decimal_string(),
         }
     }
 }
@@ -79,7 +502,6 @@ pub struct Defs(Vec<Def>);
 impl Defs {
     /// Create new definitions that have access to global variables of the given names.
     pub fn new(vars: Vec<String>) -> Self {
-        use alloc::string::ToString;
         let root = Def {
             name: "".to_string(),
             args: vars.into_iter().map(Arg::new_var).collect(),
@@ -195,7 +617,7 @@ impl Ctx {
 
     /// Insert a root filter.
     pub fn root_filter(&mut self, filter: HirFilter) {
-        self.defs.0[ROOT_ID].body = self.filter(ROOT_ID, Vec::new(), filter);
+        self.defs.0[ROOT_ID].body = fold_consts(self.filter(ROOT_ID, Vec::new(), filter));
     }
 
     fn def(&mut self, mut ancestors: Vec<DefId>, def: parse::Def) {
@@ -223,7 +645,7 @@ impl Ctx {
             self.def(ancestors.clone(), d);
         }
 
-        self.defs.0[id].body = self.filter(id, Vec::new(), def.body);
+        self.defs.0[id].body = fold_consts(self.filter(id, Vec::new(), def.body));
     }
 
     fn filter(&mut self, id: DefId, mut vars: Vec<String>, f: HirFilter) -> MirFilter {
@@ -343,3 +765,144 @@ impl Ctx {
         (result, f.1)
     }
 }
+
+/// Negate a numeric literal, the constant-folded counterpart of `Filter::Neg`.
+fn neg_num(n: &Num) -> Option<Num> {
+    match n {
+        Num::Int(i) => i.checked_neg().map(Num::Int),
+        Num::Float(f) => Some(Num::Float(-f)),
+        Num::Big(r) => Some(from_rational(r.neg())),
+    }
+}
+
+fn num_as_f64(n: &Num) -> f64 {
+    match n {
+        Num::Int(i) => *i as f64,
+        Num::Float(f) => *f,
+        Num::Big(r) => r.to_f64(),
+    }
+}
+
+fn to_rational(n: &Num) -> Option<rational::Rational> {
+    match n {
+        Num::Int(i) => Some(rational::Rational::from_bigint(rational::BigInt::from_i64(*i as i64))),
+        Num::Big(r) => Some(r.clone()),
+        Num::Float(_) => None,
+    }
+}
+
+/// Collapse a rational back to `Num::Int` when it is a whole number that
+/// fits in an `isize`, keeping `Num::Big` only when that is not the case.
+fn from_rational(r: rational::Rational) -> Num {
+    r.to_isize().map_or(Num::Big(r), Num::Int)
+}
+
+/// Fold `op` applied to two numeric literals, or return `None` to abort
+/// folding (integer overflow, division/remainder by zero): the original
+/// `Binary` node is then left untouched rather than replaced by a bogus
+/// constant. `Int`/`Big` operands are combined exactly; as soon as a
+/// `Float` is involved, both sides are widened to `f64` to match runtime
+/// arithmetic.
+fn fold_math(op: &BinaryOp, a: &Num, b: &Num) -> Option<Num> {
+    use BinaryOp::*;
+    match (a, b) {
+        (Num::Int(a), Num::Int(b)) => match op {
+            Add => a.checked_add(*b).map(Num::Int),
+            Sub => a.checked_sub(*b).map(Num::Int),
+            Mul => a.checked_mul(*b).map(Num::Int),
+            Div if *b != 0 => match a.checked_rem(*b) {
+                Some(0) => a.checked_div(*b).map(Num::Int),
+                // not evenly divisible: stay exact as a `Rational` (e.g.
+                // `1 / 3` becomes `Num::Big`) instead of widening to `f64`
+                Some(_) => to_rational(&Num::Int(*a))?.div(&to_rational(&Num::Int(*b))?).map(from_rational),
+                // `isize::MIN % -1` (and thus `isize::MIN / -1`) overflows
+                None => None,
+            },
+            Rem if *b != 0 => a.checked_rem(*b).map(Num::Int),
+            _ => None,
+        },
+        (Num::Float(_), _) | (_, Num::Float(_)) => {
+            let (a, b) = (num_as_f64(a), num_as_f64(b));
+            match op {
+                Add => Some(Num::Float(a + b)),
+                Sub => Some(Num::Float(a - b)),
+                Mul => Some(Num::Float(a * b)),
+                Div if b != 0.0 => Some(Num::Float(a / b)),
+                Rem if b != 0.0 => Some(Num::Float(a % b)),
+                _ => None,
+            }
+        }
+        // remaining combination is `Int`/`Big`: stay exact via `Rational`
+        _ => {
+            let (a, b) = (to_rational(a)?, to_rational(b)?);
+            match op {
+                Add => Some(from_rational(a.add(&b))),
+                Sub => Some(from_rational(a.sub(&b))),
+                Mul => Some(from_rational(a.mul(&b))),
+                Div => a.div(&b).map(from_rational),
+                _ => None,
+            }
+        }
+    }
+}
+
+/// Fold constant arithmetic on numeric literals in already-built MIR,
+/// e.g. turning `1 + 2 * 3` into the literal `7` outright. This shrinks
+/// the IR and speeds up filters that embed literal math. Only `Neg(Num)`
+/// and `Binary(Num, op, Num)` for the arithmetic operators are folded;
+/// comparisons are left alone, since they produce booleans rather than
+/// `Num` literals.
+pub fn fold_consts(f: MirFilter) -> MirFilter {
+    let (filter, span) = f;
+    let filter = match filter {
+        Filter::Neg(x) => {
+            let x = fold_consts(*x);
+            let folded = if let Filter::Num(n) = &x.0 { neg_num(n) } else { None };
+            match folded {
+                Some(n) => Filter::Num(n),
+                None => Filter::Neg(Box::new(x)),
+            }
+        }
+        Filter::Binary(l, op, r) => {
+            let l = fold_consts(*l);
+            let r = fold_consts(*r);
+            let folded = match (&l.0, &r.0) {
+                (Filter::Num(a), Filter::Num(b)) => fold_math(&op, a, b),
+                _ => None,
+            };
+            match folded {
+                Some(n) => Filter::Num(n),
+                None => Filter::Binary(Box::new(l), op, Box::new(r)),
+            }
+        }
+        Filter::Array(a) => Filter::Array(a.map(|a| Box::new(fold_consts(*a)))),
+        Filter::Try(x) => Filter::Try(Box::new(fold_consts(*x))),
+        Filter::Ite(if_thens, else_) => Filter::Ite(
+            if_thens
+                .into_iter()
+                .map(|(i, t)| (fold_consts(i), fold_consts(t)))
+                .collect(),
+            Box::new(fold_consts(*else_)),
+        ),
+        Filter::Fold(typ, Fold { xs, x, init, f }) => Filter::Fold(
+            typ,
+            Fold {
+                xs: Box::new(fold_consts(*xs)),
+                x,
+                init: Box::new(fold_consts(*init)),
+                f: Box::new(fold_consts(*f)),
+            },
+        ),
+        Filter::Call(call, args) => Filter::Call(call, args.into_iter().map(fold_consts).collect()),
+        Filter::Path(x, path) => Filter::Path(
+            Box::new(fold_consts(*x)),
+            path.into_iter()
+                .map(|(p, opt)| (p.map(fold_consts), opt))
+                .collect(),
+        ),
+        // `Id`, `Var`, `Num`, `Str`, `Recurse`, and `Object` have no
+        // constant-foldable subexpressions that this pass touches.
+        other => other,
+    };
+    (filter, span)
+}
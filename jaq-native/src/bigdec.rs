@@ -0,0 +1,180 @@
+//! Exact arithmetic on the decimal-string representation of `Val::Num`.
+//!
+//! `Val::Num` carries a numeric literal too large for `isize`/`f64` as its
+//! original decimal text instead of rounding it on parse. The filters here
+//! (`floor`/`round`/`ceil`, and the comparisons `sort`/`sort_by`/`group_by`/
+//! `min_by`/`max_by` rely on) need to operate on that text exactly rather
+//! than reparsing it through `f64`, so this module implements just enough
+//! arbitrary-precision decimal arithmetic for that: ordering, and
+//! floor/ceil/round to the nearest integer.
+//!
+//! Every `Val::Num` seen in this crate is a plain (optionally signed)
+//! integer or decimal literal, never exponential notation or a fraction
+//! (`Num::Big`'s rational form in `jaq-core`'s MIR constant folder is a
+//! separate, compile-time-only concern), so this only needs to handle that
+//! shape.
+
+use alloc::string::{String, ToString};
+use core::cmp::Ordering;
+
+/// Split a decimal literal into `(negative, integer_digits, fraction_digits)`,
+/// with leading zeros trimmed from the integer part (but at least one digit
+/// kept) and trailing zeros trimmed from the fraction part.
+fn split(s: &str) -> (bool, &str, &str) {
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let (int_part, frac_part) = s.split_once('.').unwrap_or((s, ""));
+    let int_part = int_part.trim_start_matches('0');
+    let int_part = if int_part.is_empty() { "0" } else { int_part };
+    let frac_part = frac_part.trim_end_matches('0');
+    (negative && !(int_part == "0" && frac_part.is_empty()), int_part, frac_part)
+}
+
+/// Compare two decimal-literal strings exactly, without rounding through `f64`.
+pub fn cmp_decimal(a: &str, b: &str) -> Ordering {
+    let (a_neg, a_int, a_frac) = split(a);
+    let (b_neg, b_int, b_frac) = split(b);
+    match (a_neg, b_neg) {
+        (true, false) => return Ordering::Less,
+        (false, true) => return Ordering::Greater,
+        _ => {}
+    }
+    let magnitude = a_int
+        .len()
+        .cmp(&b_int.len())
+        .then_with(|| a_int.cmp(b_int))
+        .then_with(|| cmp_fracs(a_frac, b_frac));
+    if a_neg {
+        magnitude.reverse()
+    } else {
+        magnitude
+    }
+}
+
+/// Compare two fractional-digit strings, treating each as right-padded with zeros.
+fn cmp_fracs(a: &str, b: &str) -> Ordering {
+    let len = a.len().max(b.len());
+    let pad = |s: &str| -> String {
+        let mut s = s.to_string();
+        while s.len() < len {
+            s.push('0');
+        }
+        s
+    };
+    pad(a).cmp(&pad(b))
+}
+
+/// Add one to an unsigned decimal digit string, e.g. `"999"` -> `"1000"`.
+fn increment(digits: &str) -> String {
+    let mut out: alloc::vec::Vec<u8> = digits.bytes().collect();
+    let mut i = out.len();
+    loop {
+        if i == 0 {
+            out.insert(0, b'1');
+            break;
+        }
+        i -= 1;
+        if out[i] == b'9' {
+            out[i] = b'0';
+        } else {
+            out[i] += 1;
+            break;
+        }
+    }
+    String::from_utf8(out).unwrap()
+}
+
+/// Truncate a decimal literal toward zero, dropping any fractional part.
+fn truncate(negative: bool, int_part: &str) -> String {
+    if negative && int_part != "0" {
+        alloc::format!("-{int_part}")
+    } else {
+        int_part.to_string()
+    }
+}
+
+/// `floor`: round toward negative infinity, as an exact decimal integer string.
+pub fn floor_decimal(s: &str) -> String {
+    let (negative, int_part, frac_part) = split(s);
+    if frac_part.is_empty() {
+        return truncate(negative, int_part);
+    }
+    if negative {
+        alloc::format!("-{}", increment(int_part))
+    } else {
+        int_part.to_string()
+    }
+}
+
+/// `ceil`: round toward positive infinity, as an exact decimal integer string.
+pub fn ceil_decimal(s: &str) -> String {
+    let (negative, int_part, frac_part) = split(s);
+    if frac_part.is_empty() {
+        return truncate(negative, int_part);
+    }
+    if negative {
+        truncate(true, int_part)
+    } else {
+        increment(int_part)
+    }
+}
+
+/// `round`: round to the nearest integer, ties away from zero.
+pub fn round_decimal(s: &str) -> String {
+    let (negative, int_part, frac_part) = split(s);
+    if frac_part.is_empty() {
+        return truncate(negative, int_part);
+    }
+    let round_up = frac_part.as_bytes()[0] >= b'5';
+    if !round_up {
+        return truncate(negative, int_part);
+    }
+    let bumped = increment(int_part);
+    if negative {
+        alloc::format!("-{bumped}")
+    } else {
+        bumped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cmp_decimal_orders_by_magnitude_not_text_length() {
+        assert_eq!(cmp_decimal("9", "10"), Ordering::Less);
+        assert_eq!(cmp_decimal("100000000000000000000", "99999999999999999999"), Ordering::Greater);
+        assert_eq!(cmp_decimal("-5", "3"), Ordering::Less);
+        assert_eq!(cmp_decimal("-10", "-2"), Ordering::Less);
+        assert_eq!(cmp_decimal("1.50", "1.5"), Ordering::Equal);
+        assert_eq!(cmp_decimal("1.4", "1.45"), Ordering::Less);
+    }
+
+    #[test]
+    fn floor_ceil_round_match_libc_semantics() {
+        assert_eq!(floor_decimal("10000000000000000000.7"), "10000000000000000000");
+        assert_eq!(ceil_decimal("10000000000000000000.1"), "10000000000000000001");
+        assert_eq!(round_decimal("10000000000000000000.5"), "10000000000000000001");
+        assert_eq!(floor_decimal("-1.2"), "-2");
+        assert_eq!(ceil_decimal("-1.2"), "-1");
+        assert_eq!(round_decimal("-1.5"), "-2");
+        assert_eq!(floor_decimal("99999999999999999999"), "99999999999999999999");
+    }
+
+    #[test]
+    fn increment_carries_across_all_nines() {
+        assert_eq!(increment("999"), "1000");
+        assert_eq!(increment("0"), "1");
+        assert_eq!(increment("1099"), "1100");
+    }
+
+    #[test]
+    fn split_strips_redundant_zeros() {
+        assert_eq!(split("007.100"), (false, "7", "1"));
+        assert_eq!(split("-0.0"), (false, "0", ""));
+        assert_eq!(split("0"), (false, "0", ""));
+    }
+}
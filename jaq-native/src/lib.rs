@@ -7,13 +7,17 @@ extern crate alloc;
 #[cfg(feature = "std")]
 extern crate std;
 
+mod bigdec;
 #[cfg(feature = "regex")]
 mod regex;
+#[cfg(all(feature = "random", feature = "std"))]
+mod random;
 #[cfg(feature = "time")]
 mod time;
 
 use alloc::string::{String, ToString};
-use alloc::{boxed::Box, rc::Rc, vec::Vec};
+use alloc::{boxed::Box, format, rc::Rc, vec::Vec};
+use core::cmp::Ordering;
 use jaq_core::results::{box_once, run_if_ok, then};
 use jaq_core::{Ctx, FilterT, Native, RunPtr, UpdatePtr};
 use jaq_core::{Error, Val, ValR, ValRs};
@@ -32,7 +36,34 @@ pub fn minimal() -> impl Iterator<Item = (String, usize, Native)> {
 /// but also `now`, `debug`, `fromdateiso8601`, ...
 ///
 /// Does not return filters from the standard library, such as `map`.
-#[cfg(all(feature = "std", feature = "log", feature = "regex", feature = "time"))]
+#[cfg(all(
+    feature = "std",
+    feature = "log",
+    feature = "regex",
+    feature = "time",
+    feature = "random"
+))]
+pub fn core() -> impl Iterator<Item = (String, usize, Native)> {
+    // Reseed to the deterministic default so that this construction's
+    // random stream does not inherit state mutated by a previous one.
+    random::reset_default();
+    minimal()
+        .chain(run(STD))
+        .chain(upd(LOG))
+        .chain(run(REGEX))
+        .chain(run(TIME))
+        .chain(run(RANDOM))
+}
+
+/// Same as the `random`-enabled `core()` above, but without `random`/`randint`/
+/// `shuffle`/`srandom` for builds that don't enable the `random` feature.
+#[cfg(all(
+    feature = "std",
+    feature = "log",
+    feature = "regex",
+    feature = "time",
+    not(feature = "random")
+))]
 pub fn core() -> impl Iterator<Item = (String, usize, Native)> {
     minimal()
         .chain(run(STD))
@@ -60,12 +91,47 @@ fn rc_unwrap_or_clone<T: Clone>(a: Rc<T>) -> T {
     Rc::try_unwrap(a).unwrap_or_else(|a| (*a).clone())
 }
 
+/// Compare two `Val`s exactly: a `Val::Num` compared against another
+/// `Val::Num` or a `Val::Int` is compared via [`bigdec::cmp_decimal`] on
+/// their decimal text rather than `Val`'s own `Ord`, which would otherwise
+/// need to reparse `Val::Num` through `f64` and so could round. Any other
+/// combination (including one involving a `Val::Float`) falls back to
+/// `Val`'s own ordering, matching how `jaq-core`'s constant folder only
+/// keeps `Int`/`Big` mixes exact and lets `Float` stay approximate.
+fn cmp_val_exact(a: &Val, b: &Val) -> Ordering {
+    match (a, b) {
+        (Val::Num(a), Val::Num(b)) => bigdec::cmp_decimal(a, b),
+        (Val::Num(a), Val::Int(b)) => bigdec::cmp_decimal(a, &b.to_string()),
+        (Val::Int(a), Val::Num(b)) => bigdec::cmp_decimal(&a.to_string(), b),
+        _ => a.cmp(b),
+    }
+}
+
+/// Compare two same-length `Val` tuples lexicographically, via [`cmp_val_exact`].
+fn cmp_vals_exact(a: &[Val], b: &[Val]) -> Ordering {
+    a.iter()
+        .zip(b.iter())
+        .map(|(x, y)| cmp_val_exact(x, y))
+        .find(|o| *o != Ordering::Equal)
+        .unwrap_or_else(|| a.len().cmp(&b.len()))
+}
+
 /// Sort array by the given function.
 fn sort_by<'a>(xs: &mut [Val], f: impl Fn(Val) -> ValRs<'a>) -> Result<(), Error> {
     // Some(e) iff an error has previously occurred
     let mut err = None;
-    xs.sort_by_cached_key(|x| run_if_ok(x.clone(), &mut err, &f));
-    err.map_or(Ok(()), Err)
+    let mut keyed: Vec<(Vec<Val>, Val)> = xs
+        .iter()
+        .map(|x| (run_if_ok(x.clone(), &mut err, &f), x.clone()))
+        .collect();
+    if let Some(e) = err {
+        return Err(e);
+    }
+    keyed.sort_by(|(k1, _), (k2, _)| cmp_vals_exact(k1, k2));
+    for (slot, (_, v)) in xs.iter_mut().zip(keyed) {
+        *slot = v;
+    }
+    Ok(())
 }
 
 /// Group an array by the given function.
@@ -79,7 +145,7 @@ fn group_by<'a>(xs: Vec<Val>, f: impl Fn(Val) -> ValRs<'a>) -> ValR {
         return Err(err);
     }
 
-    yx.sort_by(|(y1, _), (y2, _)| y1.cmp(y2));
+    yx.sort_by(|(y1, _), (y2, _)| cmp_vals_exact(y1, y2));
 
     use itertools::Itertools;
     let grouped = yx
@@ -131,6 +197,171 @@ where
     f(&s, other).map_or_else(|| s.clone(), |stripped| Rc::new(stripped.into()))
 }
 
+/// Percent-encode every byte outside RFC 3986's unreserved set
+/// (`A-Z a-z 0-9 - _ . ~`), as `@uri` does.
+fn percent_encode(s: &str) -> String {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(b as char)
+            }
+            _ => {
+                out.push('%');
+                out.push(HEX[(b >> 4) as usize] as char);
+                out.push(HEX[(b & 0xf) as usize] as char);
+            }
+        }
+    }
+    out
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encode bytes with the standard, padded base64 alphabet, as `@base64` does.
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        out.push(BASE64_ALPHABET[(n >> 18 & 0x3f) as usize] as char);
+        out.push(BASE64_ALPHABET[(n >> 12 & 0x3f) as usize] as char);
+        out.push(if chunk.len() > 1 {
+            BASE64_ALPHABET[(n >> 6 & 0x3f) as usize] as char
+        } else {
+            '='
+        });
+        out.push(if chunk.len() > 2 {
+            BASE64_ALPHABET[(n & 0x3f) as usize] as char
+        } else {
+            '='
+        });
+    }
+    out
+}
+
+/// Decode standard base64 text, as `@base64d` does, erroring on malformed input.
+fn base64_decode(s: &str) -> Result<Vec<u8>, Error> {
+    fn digit(c: u8) -> Option<u32> {
+        match c {
+            b'A'..=b'Z' => Some((c - b'A') as u32),
+            b'a'..=b'z' => Some((c - b'a' + 26) as u32),
+            b'0'..=b'9' => Some((c - b'0' + 52) as u32),
+            b'+' => Some(62),
+            b'/' => Some(63),
+            _ => None,
+        }
+    }
+    let invalid = || Error::Custom(format!("{s} is not valid base64"));
+    let chars: Vec<u8> = s.bytes().filter(|b| *b != b'=' && !b.is_ascii_whitespace()).collect();
+    let mut out = Vec::with_capacity(chars.len() * 3 / 4);
+    for chunk in chars.chunks(4) {
+        if chunk.len() < 2 {
+            return Err(invalid());
+        }
+        let mut n = 0u32;
+        for c in chunk {
+            n = n << 6 | digit(*c).ok_or_else(invalid)?;
+        }
+        n <<= 6 * (4 - chunk.len());
+        let bytes = [(n >> 16) as u8, (n >> 8) as u8, n as u8];
+        out.extend_from_slice(&bytes[..chunk.len() - 1]);
+    }
+    Ok(out)
+}
+
+// intermediate stream of `(from, to, by)` triples feeding `range/3`
+type ValTriples<'a> = Box<dyn Iterator<Item = Result<(Val, Val, Val), Error>> + 'a>;
+
+/// Walk `from` toward `to` in steps of `by`, as `range/3` does.
+fn range_step<'a>(from: Val, to: Val, by: Val) -> Result<ValRs<'a>, Error> {
+    match (from, to, by) {
+        (Val::Int(from), Val::Int(to), Val::Int(by)) => {
+            Ok(Box::new(int_range_step(from, to, by).map(|i| Ok(Val::Int(i)))))
+        }
+        (from, to, by) => {
+            let (from, to, by) = (as_f64(&from)?, as_f64(&to)?, as_f64(&by)?);
+            Ok(Box::new(float_range_step(from, to, by).map(|f| Ok(Val::Float(f)))))
+        }
+    }
+}
+
+fn int_range_step(from: isize, to: isize, by: isize) -> impl Iterator<Item = isize> {
+    // `x` becomes `None` once stepping further would overflow `isize`, so
+    // the last in-range value is still yielded instead of panicking.
+    let mut x = Some(from);
+    core::iter::from_fn(move || {
+        let cur = x?;
+        let cont = match by {
+            by if by > 0 => cur < to,
+            by if by < 0 => cur > to,
+            _ => false,
+        };
+        if !cont {
+            x = None;
+            return None;
+        }
+        x = cur.checked_add(by);
+        Some(cur)
+    })
+}
+
+fn float_range_step(from: f64, to: f64, by: f64) -> impl Iterator<Item = f64> {
+    let mut x = from;
+    core::iter::from_fn(move || {
+        let cont = if by > 0.0 {
+            x < to
+        } else if by < 0.0 {
+            x > to
+        } else {
+            false
+        };
+        cont.then(|| {
+            let out = x;
+            x += by;
+            out
+        })
+    })
+}
+
+/// Parse a `Val::Num`'s decimal string exactly: as an `isize` whenever the
+/// literal fits one without rounding, falling back to `f64` only when it
+/// doesn't (a huge integer, or a fractional literal). Plain `Val::from_dec_str`
+/// reparses through `f64` unconditionally, which loses precision on integers
+/// that are exactly representable in `isize` but not in `f64`'s 53 mantissa
+/// bits (e.g. `9007199254740993`, which is `2^53 + 1`); this keeps those exact.
+pub(crate) fn exact_num(n: &str) -> Val {
+    if !n.contains(['.', 'e', 'E']) {
+        if let Ok(i) = n.parse::<isize>() {
+            return Val::Int(i);
+        }
+    }
+    Val::Float(n.parse().unwrap_or(0.0))
+}
+
+/// `floor`/`round`/`ceil` on a `Val::Num` exactly, via `bigdec`, instead of
+/// through `Val::round`'s `f64` path, which would round a literal like
+/// `10000000000000000000.1` before `ceil` ever saw it. Other `Val`s keep
+/// using `Val::round` and its own `f64` semantics.
+fn round_val(v: &Val, f: impl Fn(f64) -> f64, d: impl Fn(&str) -> String) -> ValR {
+    match v {
+        Val::Num(n) => Ok(Val::Num(d(n).as_str().into())),
+        _ => v.clone().round(f),
+    }
+}
+
+/// Coerce a numeric `Val` to `f64`, for arithmetic that cannot stay in `isize`.
+fn as_f64(v: &Val) -> Result<f64, Error> {
+    match v {
+        Val::Int(i) => Ok(*i as f64),
+        Val::Float(f) => Ok(*f),
+        Val::Num(n) => as_f64(&exact_num(n)),
+        _ => Err(Error::Custom(format!("{v} is not a number"))),
+    }
+}
+
 const CORE_RUN: &[(&str, usize, RunPtr)] = &[
     ("inputs", 0, |_, cv| {
         Box::new(cv.0.inputs().map(|r| r.map_err(Error::Parse)))
@@ -139,9 +370,9 @@ const CORE_RUN: &[(&str, usize, RunPtr)] = &[
     ("keys_unsorted", 0, |_, cv| {
         box_once(cv.1.keys_unsorted().map(Val::arr))
     }),
-    ("floor", 0, |_, cv| box_once(cv.1.round(|f| f.floor()))),
-    ("round", 0, |_, cv| box_once(cv.1.round(|f| f.round()))),
-    ("ceil", 0, |_, cv| box_once(cv.1.round(|f| f.ceil()))),
+    ("floor", 0, |_, cv| box_once(round_val(&cv.1, f64::floor, bigdec::floor_decimal))),
+    ("round", 0, |_, cv| box_once(round_val(&cv.1, f64::round, bigdec::round_decimal))),
+    ("ceil", 0, |_, cv| box_once(round_val(&cv.1, f64::ceil, bigdec::ceil_decimal))),
     ("fromjson", 0, |_, cv| box_once(cv.1.from_json())),
     ("tojson", 0, |_, cv| {
         box_once(Ok(Val::str(cv.1.to_string())))
@@ -160,7 +391,7 @@ const CORE_RUN: &[(&str, usize, RunPtr)] = &[
     ("reverse", 0, |_, cv| {
         box_once(cv.1.mutate_arr(|a| a.reverse()))
     }),
-    ("sort", 0, |_, cv| box_once(cv.1.mutate_arr(|a| a.sort()))),
+    ("sort", 0, |_, cv| box_once(cv.1.mutate_arr(|a| a.sort_by(cmp_val_exact)))),
     ("sort_by", 1, |args, cv| {
         box_once(cv.1.try_mutate_arr(|arr| sort_by(arr, |v| args.get(0).run((cv.0.clone(), v)))))
     }),
@@ -172,13 +403,13 @@ const CORE_RUN: &[(&str, usize, RunPtr)] = &[
     ("min_by", 1, |args, cv| {
         let f = |v| args.get(0).run((cv.0.clone(), v));
         then(cv.1.into_arr().map(rc_unwrap_or_clone), |arr| {
-            box_once(cmp_by(arr, f, |my, y| y < my))
+            box_once(cmp_by(arr, f, |my, y| cmp_vals_exact(y, my) == Ordering::Less))
         })
     }),
     ("max_by", 1, |args, cv| {
         let f = |v| args.get(0).run((cv.0.clone(), v));
         then(cv.1.into_arr().map(rc_unwrap_or_clone), |arr| {
-            box_once(cmp_by(arr, f, |my, y| y >= my))
+            box_once(cmp_by(arr, f, |my, y| cmp_vals_exact(y, my) != Ordering::Less))
         })
     }),
     ("has", 1, |args, cv| {
@@ -218,6 +449,29 @@ const CORE_RUN: &[(&str, usize, RunPtr)] = &[
         let f = |(l, u)| (l..u).map(|i| Ok(Val::Int(i)));
         Box::new(ranges.flat_map(move |range| then(range, |lu| Box::new(f(lu)))))
     }),
+    // `range(from; to; by)` walks `from` toward `to` in steps of `by`:
+    // it yields while the value is `< to` for a positive `by`, `> to` for
+    // a negative `by`, and nothing at all for a `by` of zero (so it cannot
+    // loop forever). Unlike `range/2`, the step need not be an integer,
+    // so the inner walk is driven by addition rather than an `isize` range.
+    ("range", 3, |args, cv| {
+        let pairs = args.get(0).cartesian(args.get(1), cv.clone());
+        let triples = pairs.flat_map(move |(from, to)| -> ValTriples {
+            match (from, to) {
+                (Ok(from), Ok(to)) => {
+                    let bys = args.get(2).run(cv.clone());
+                    Box::new(bys.map(move |by| Ok((from.clone(), to.clone(), by?))))
+                }
+                (Err(e), _) | (_, Err(e)) => Box::new(core::iter::once(Err(e))),
+            }
+        });
+        Box::new(triples.flat_map(|t| {
+            then(t, |(from, to, by)| match range_step(from, to, by) {
+                Ok(it) => it,
+                Err(e) => Box::new(core::iter::once(Err(e))) as ValRs,
+            })
+        }))
+    }),
     ("recurse_inner", 1, |args, cv| {
         args.get(0).recurse(true, false, cv)
     }),
@@ -246,6 +500,21 @@ const CORE_RUN: &[(&str, usize, RunPtr)] = &[
             })))
         }))
     }),
+    ("uri", 0, |_, cv| {
+        box_once(cv.1.as_str().map(|s| Val::str(percent_encode(s))))
+    }),
+    ("base64", 0, |_, cv| {
+        box_once(cv.1.as_str().map(|s| Val::str(base64_encode(s.as_bytes()))))
+    }),
+    ("base64d", 0, |_, cv| {
+        then(cv.1.as_str(), |s| {
+            box_once(base64_decode(s).and_then(|bytes| {
+                String::from_utf8(bytes)
+                    .map(Val::str)
+                    .map_err(|e| Error::Custom(format!("{e}")))
+            }))
+        })
+    }),
 ];
 
 #[cfg(feature = "std")]
@@ -287,6 +556,18 @@ const REGEX: &[(&str, usize, RunPtr)] = &[
     }),
 ];
 
+#[cfg(all(feature = "random", feature = "std"))]
+const RANDOM: &[(&str, usize, RunPtr)] = &[
+    ("random", 0, |_, _| box_once(random::random())),
+    ("randint", 1, |args, cv| {
+        Box::new(args.get(0).run(cv).map(|n| random::randint(&n?)))
+    }),
+    ("shuffle", 0, |_, cv| box_once(random::shuffle(&cv.1))),
+    ("srandom", 1, |args, cv| {
+        Box::new(args.get(0).run(cv).map(|s| random::srandom(&s?)))
+    }),
+];
+
 #[cfg(feature = "time")]
 const TIME: &[(&str, usize, RunPtr)] = &[
     ("fromdateiso8601", 0, |_, cv| {
@@ -295,6 +576,29 @@ const TIME: &[(&str, usize, RunPtr)] = &[
     ("todateiso8601", 0, |_, cv| {
         box_once(time::to_iso8601(&cv.1).map(Val::str))
     }),
+    ("gmtime", 0, |_, cv| box_once(time::gmtime(&cv.1))),
+    ("mktime", 0, |_, cv| box_once(time::mktime(&cv.1))),
+    ("localtime", 0, |_, cv| box_once(time::localtime(&cv.1))),
+    ("strftime", 1, |args, cv| {
+        let fmts = args.get(0).run(cv.clone());
+        Box::new(fmts.map(move |fmt| time::strftime(&cv.1, fmt?.as_str()?)))
+    }),
+    ("strptime", 1, |args, cv| {
+        let fmts = args.get(0).run(cv.clone());
+        Box::new(fmts.map(move |fmt| time::strptime(cv.1.as_str()?, fmt?.as_str()?)))
+    }),
+    ("date", 0, |_, cv| {
+        box_once(time::to_iso8601(&cv.1).map(Val::str))
+    }),
+    ("todate", 0, |_, cv| {
+        box_once(time::to_iso8601(&cv.1).map(Val::str))
+    }),
+    ("dateadd", 2, |args, cv| {
+        let prod = args.get(0).cartesian(args.get(1), cv.clone());
+        Box::new(prod.map(move |(unit, amount)| {
+            time::dateadd(&cv.1, unit?.as_str()?, amount?.as_int()? as f64)
+        }))
+    }),
 ];
 
 const CORE_UPDATE: &[(&str, usize, RunPtr, UpdatePtr)] = &[
@@ -331,3 +635,102 @@ const LOG: &[(&str, usize, RunPtr, UpdatePtr)] = &[(
     |_, cv| box_once(Ok(debug(cv.1))),
     |_, cv, f| f(debug(cv.1)),
 )];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_num_keeps_large_integers_exact() {
+        // isize::MAX does not fit exactly in f64's 53-bit mantissa, so a
+        // naive reparse through f64 would round it to a different integer.
+        assert_eq!(exact_num("9223372036854775807"), Val::Int(isize::MAX));
+    }
+
+    #[test]
+    fn exact_num_falls_back_to_float_for_fractional_literals() {
+        assert_eq!(exact_num("1.5"), Val::Float(1.5));
+    }
+
+    #[test]
+    fn round_val_keeps_oversized_num_exact() {
+        // exact_num/Val::round would reparse this through f64 and round it;
+        // round_val must floor/ceil the decimal text itself instead.
+        let n = Val::Num("10000000000000000000.7".into());
+        assert_eq!(
+            round_val(&n, f64::floor, bigdec::floor_decimal).unwrap(),
+            Val::Num("10000000000000000000".into())
+        );
+        assert_eq!(
+            round_val(&n, f64::ceil, bigdec::ceil_decimal).unwrap(),
+            Val::Num("10000000000000000001".into())
+        );
+    }
+
+    #[test]
+    fn cmp_val_exact_orders_oversized_nums_by_magnitude() {
+        let a = Val::Num("100000000000000000000".into());
+        let b = Val::Num("99999999999999999999".into());
+        assert_eq!(cmp_val_exact(&a, &b), Ordering::Greater);
+    }
+
+    #[test]
+    fn int_range_step_ascends() {
+        let v: Vec<_> = int_range_step(0, 10, 3).collect();
+        assert_eq!(v, [0, 3, 6, 9]);
+    }
+
+    #[test]
+    fn int_range_step_descends() {
+        let v: Vec<_> = int_range_step(10, 0, -3).collect();
+        assert_eq!(v, [10, 7, 4, 1]);
+    }
+
+    #[test]
+    fn int_range_step_zero_by_is_empty() {
+        let v: Vec<_> = int_range_step(0, 10, 0).collect();
+        assert_eq!(v, []);
+    }
+
+    #[test]
+    fn int_range_step_stops_before_overflow() {
+        let v: Vec<_> = int_range_step(isize::MAX - 5, isize::MAX, 10).collect();
+        // yields the last in-range value without panicking on the next (overflowing) step
+        assert_eq!(v, [isize::MAX - 5]);
+    }
+
+    #[test]
+    fn range_step_keeps_ints_exact() {
+        let it = range_step(Val::Int(0), Val::Int(6), Val::Int(2)).unwrap();
+        let got: Vec<_> = it.map(|v| v.unwrap()).collect();
+        assert_eq!(got, [Val::Int(0), Val::Int(2), Val::Int(4)]);
+    }
+
+    #[test]
+    fn range_step_falls_back_to_float_on_mixed_types() {
+        let it = range_step(Val::Int(0), Val::Float(1.0), Val::Float(0.5)).unwrap();
+        let got: Vec<_> = it.map(|v| v.unwrap()).collect();
+        assert_eq!(got, [Val::Float(0.0), Val::Float(0.5)]);
+    }
+
+    #[test]
+    fn percent_encode_keeps_unreserved_and_escapes_rest() {
+        assert_eq!(percent_encode("a-z_0~9"), "a-z_0~9");
+        assert_eq!(percent_encode(" /"), "%20%2F");
+    }
+
+    #[test]
+    fn base64_round_trips_with_padding() {
+        assert_eq!(base64_encode(b"f"), "Zg==");
+        assert_eq!(base64_encode(b"fo"), "Zm8=");
+        assert_eq!(base64_encode(b"foo"), "Zm9v");
+        assert_eq!(base64_decode("Zg==").unwrap(), b"f");
+        assert_eq!(base64_decode("Zm8=").unwrap(), b"fo");
+        assert_eq!(base64_decode("Zm9v").unwrap(), b"foo");
+    }
+
+    #[test]
+    fn base64_decode_rejects_malformed_input() {
+        assert!(base64_decode("not valid base64!").is_err());
+    }
+}
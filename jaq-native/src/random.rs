@@ -0,0 +1,260 @@
+//! Seeded pseudo-random number generation.
+//!
+//! jq itself has no random filters, but data-shaping pipelines frequently
+//! need sampling or shuffling. Rather than pull in an external RNG crate,
+//! this implements ISAAC ("Indirection, Shift, Accumulate, Add, and Count"),
+//! Bob Jenkins' generator, keeping the `random` feature as dependency-free
+//! as the rest of jaq-native.
+//!
+//! The generator state should live in `Ctx` so that two independently
+//! constructed filter sets never share a stream. `jaq_core::Ctx` is defined
+//! outside this crate (it ships from `jaq-core`'s crate root, which isn't
+//! part of this source tree) and carries no slot for native-filter-owned
+//! state, so there is no way to store the engine on `Ctx` from here. As the
+//! next best thing, the state lives behind a single thread-local cell, and
+//! [`reset_default`] reseeds it to [`DEFAULT_SEED`] every time a filter set
+//! is (re)built (see `core()` in `lib.rs`), so that each *construction* is
+//! deterministic even though calls within it still share one advancing
+//! stream, and `srandom` reseeds that same stream explicitly.
+
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use jaq_core::{Error, Val, ValR};
+
+const SIZE: usize = 256;
+
+struct Isaac {
+    mm: [u32; SIZE],
+    aa: u32,
+    bb: u32,
+    cc: u32,
+    out: [u32; SIZE],
+    used: usize,
+}
+
+impl Isaac {
+    fn new(seed: &[u32]) -> Self {
+        let mut mm = [0u32; SIZE];
+        if !seed.is_empty() {
+            for (i, m) in mm.iter_mut().enumerate() {
+                *m = seed[i % seed.len()];
+            }
+        }
+        let mut isaac = Isaac {
+            mm,
+            aa: 0,
+            bb: 0,
+            cc: 0,
+            out: [0; SIZE],
+            used: SIZE,
+        };
+        isaac.mix_seed();
+        // two warm-up refills, discarded, so the first real output
+        // does not directly expose the mixed seed
+        isaac.refill();
+        isaac.refill();
+        isaac
+    }
+
+    /// Mix eight golden-ratio-seeded registers into `mm`, as in the reference ISAAC seeding.
+    fn mix_seed(&mut self) {
+        let mut regs = [0x9e3779b9u32; 8];
+        for _ in 0..4 {
+            mix(&mut regs);
+        }
+        for _pass in 0..2 {
+            for i in (0..SIZE).step_by(8) {
+                for (j, r) in regs.iter_mut().enumerate() {
+                    *r = r.wrapping_add(self.mm[i + j]);
+                }
+                mix(&mut regs);
+                for (j, r) in regs.iter().enumerate() {
+                    self.mm[i + j] = *r;
+                }
+            }
+        }
+    }
+
+    /// Refill the 256-word output buffer using the ISAAC recurrence.
+    fn refill(&mut self) {
+        self.cc = self.cc.wrapping_add(1);
+        self.bb = self.bb.wrapping_add(self.cc);
+        for i in 0..SIZE {
+            let x = self.mm[i];
+            self.aa = match i % 4 {
+                0 => self.aa ^ (self.aa << 13),
+                1 => self.aa ^ (self.aa >> 6),
+                2 => self.aa ^ (self.aa << 2),
+                _ => self.aa ^ (self.aa >> 16),
+            };
+            self.aa = self.aa.wrapping_add(self.mm[(i + 128) % SIZE]);
+            let y = self.mm[((x >> 2) as usize) & 255]
+                .wrapping_add(self.aa)
+                .wrapping_add(self.bb);
+            self.mm[i] = y;
+            self.bb = self.mm[((y >> 10) as usize) & 255].wrapping_add(x);
+            self.out[i] = self.bb;
+        }
+        self.used = 0;
+    }
+
+    fn next_u32(&mut self) -> u32 {
+        if self.used >= SIZE {
+            self.refill();
+        }
+        let v = self.out[self.used];
+        self.used += 1;
+        v
+    }
+
+    fn next_f64(&mut self) -> f64 {
+        self.next_u32() as f64 / 2f64.powi(32)
+    }
+}
+
+/// Jenkins' mixing round for seeding, applied to eight accumulator registers.
+fn mix(r: &mut [u32; 8]) {
+    r[0] ^= r[1] << 11;
+    r[3] = r[3].wrapping_add(r[0]);
+    r[1] = r[1].wrapping_add(r[2]);
+    r[1] ^= r[2] >> 2;
+    r[4] = r[4].wrapping_add(r[1]);
+    r[2] = r[2].wrapping_add(r[3]);
+    r[2] ^= r[3] << 8;
+    r[5] = r[5].wrapping_add(r[2]);
+    r[3] = r[3].wrapping_add(r[4]);
+    r[3] ^= r[4] >> 16;
+    r[6] = r[6].wrapping_add(r[3]);
+    r[4] = r[4].wrapping_add(r[5]);
+    r[4] ^= r[5] << 10;
+    r[7] = r[7].wrapping_add(r[4]);
+    r[5] = r[5].wrapping_add(r[6]);
+    r[5] ^= r[6] >> 4;
+    r[0] = r[0].wrapping_add(r[5]);
+    r[6] = r[6].wrapping_add(r[7]);
+    r[6] ^= r[7] << 8;
+    r[1] = r[1].wrapping_add(r[6]);
+    r[7] = r[7].wrapping_add(r[0]);
+    r[7] ^= r[0] >> 9;
+    r[2] = r[2].wrapping_add(r[7]);
+    r[0] = r[0].wrapping_add(r[1]);
+}
+
+/// Fixed default seed, so that `core()`-built instances behave
+/// deterministically until `srandom` is called.
+const DEFAULT_SEED: u32 = 0x6a61_7175; // b"jaqu"
+
+std::thread_local! {
+    static RNG: RefCell<Isaac> = RefCell::new(Isaac::new(&[DEFAULT_SEED]));
+}
+
+/// Reseed the shared generator to [`DEFAULT_SEED`], so that a freshly built
+/// filter set starts from the same deterministic stream regardless of what
+/// any previously built filter set did to it.
+pub(crate) fn reset_default() {
+    RNG.with(|rng| *rng.borrow_mut() = Isaac::new(&[DEFAULT_SEED]));
+}
+
+fn seed_words(v: &Val) -> Result<Vec<u32>, Error> {
+    let n = match v {
+        Val::Int(i) => *i as i64,
+        Val::Float(f) => *f as i64,
+        Val::Num(n) => match crate::exact_num(n) {
+            Val::Int(i) => i as i64,
+            Val::Float(f) => f as i64,
+            _ => return Err(Error::Custom(alloc::format!("{v} is not a valid seed"))),
+        },
+        _ => return Err(Error::Custom(alloc::format!("{v} is not a valid seed"))),
+    };
+    Ok(Vec::from([n as u32, (n >> 32) as u32]))
+}
+
+/// `srandom(seed)`: reseed the shared generator, returning the input unchanged.
+pub fn srandom(v: &Val) -> ValR {
+    let words = seed_words(v)?;
+    RNG.with(|rng| *rng.borrow_mut() = Isaac::new(&words));
+    Ok(v.clone())
+}
+
+/// `random`: a float in `[0, 1)`.
+pub fn random() -> ValR {
+    Ok(Val::Float(RNG.with(|rng| rng.borrow_mut().next_f64())))
+}
+
+/// `randint(n)`: an integer in `[0, n)`.
+pub fn randint(n: &Val) -> ValR {
+    let n = n.as_int()?;
+    if n <= 0 {
+        return Err(Error::Custom(alloc::format!(
+            "randint argument must be positive, got {n}"
+        )));
+    }
+    let r = RNG.with(|rng| rng.borrow_mut().next_u32());
+    Ok(Val::Int((r as usize % n as usize) as isize))
+}
+
+/// `shuffle`: randomly permute an array (Fisher-Yates).
+pub fn shuffle(v: &Val) -> ValR {
+    let mut xs: Vec<Val> = v.clone().into_arr()?.as_ref().clone();
+    for i in (1..xs.len()).rev() {
+        let r = RNG.with(|rng| rng.borrow_mut().next_u32());
+        let j = r as usize % (i + 1);
+        xs.swap(i, j);
+    }
+    Ok(Val::arr(xs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn srandom_with_same_seed_reproduces_the_same_stream() {
+        srandom(&Val::Int(42)).unwrap();
+        let a: Vec<_> = (0..5).map(|_| random().unwrap()).collect();
+        srandom(&Val::Int(42)).unwrap();
+        let b: Vec<_> = (0..5).map(|_| random().unwrap()).collect();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn srandom_with_different_seeds_diverges() {
+        srandom(&Val::Int(1)).unwrap();
+        let a = random().unwrap();
+        srandom(&Val::Int(2)).unwrap();
+        let b = random().unwrap();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn reset_default_reproduces_the_default_stream() {
+        reset_default();
+        let a: Vec<_> = (0..5).map(|_| random().unwrap()).collect();
+        reset_default();
+        let b: Vec<_> = (0..5).map(|_| random().unwrap()).collect();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn randint_stays_in_range() {
+        reset_default();
+        for _ in 0..100 {
+            let Val::Int(n) = randint(&Val::Int(7)).unwrap() else {
+                panic!("randint must return an int")
+            };
+            assert!((0..7).contains(&n));
+        }
+    }
+
+    #[test]
+    fn shuffle_is_a_permutation() {
+        reset_default();
+        let input = Val::arr(Vec::from([Val::Int(1), Val::Int(2), Val::Int(3), Val::Int(4)]));
+        let mut shuffled = shuffle(&input).unwrap().into_arr().unwrap().as_ref().clone();
+        shuffled.sort_by_key(|v| match v {
+            Val::Int(n) => *n,
+            _ => unreachable!(),
+        });
+        assert_eq!(shuffled, [Val::Int(1), Val::Int(2), Val::Int(3), Val::Int(4)]);
+    }
+}
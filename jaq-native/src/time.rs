@@ -1,5 +1,6 @@
 use alloc::format;
 use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 use jaq_core::{Error, Val, ValR};
 
 /// Parse an ISO-8601 timestamp string to a number holding the equivalent UNIX timestamp
@@ -30,25 +31,468 @@ pub fn to_iso8601(v: &Val) -> Result<String, Error> {
         })
         .encode();
 
-    let fai1 = |e| Error::Custom(format!("cannot format {v} as ISO-8601 timestamp: {e}"));
-    let fai2 = |e| Error::Custom(format!("cannot format {v} as ISO-8601 timestamp: {e}"));
+    let fai = |e| Error::Custom(format!("cannot format {v} as ISO-8601 timestamp: {e}"));
 
     match v {
-        Val::Num(n) => to_iso8601(&Val::from_dec_str(n)),
+        Val::Num(n) => to_iso8601(&crate::exact_num(n)),
         Val::Float(f) => {
             let f_ns = (f * 1_000_000_000_f64).round() as i128;
             OffsetDateTime::from_unix_timestamp_nanos(f_ns)
-                .map_err(fai1)?
+                .map_err(fai)?
                 .format(&iso8601::Iso8601::DEFAULT)
-                .map_err(fai2)
+                .map_err(fai)
         }
         Val::Int(i) => {
             let iso8601_fmt_s = iso8601::Iso8601::<SECONDS_CONFIG>;
             OffsetDateTime::from_unix_timestamp(*i as i64)
-                .map_err(fai1)?
+                .map_err(fai)?
                 .format(&iso8601_fmt_s)
-                .map_err(fai2)
+                .map_err(fai)
         }
         _ => todo!(),
     }
 }
+
+/// Number of elements in a jq "broken-down time" array:
+/// `[sec, min, hour, mday, mon, year-1900, wday, yday]`.
+const BROKEN_DOWN_LEN: usize = 8;
+
+/// Convert a `Val` holding a UNIX timestamp (`Int`, `Float`, or `Num`) to an `OffsetDateTime`.
+fn to_offset_date_time(v: &Val) -> Result<time::OffsetDateTime, Error> {
+    use time::OffsetDateTime;
+    let oor = |e| Error::Custom(format!("{v} is out of range for a timestamp: {e}"));
+    match v {
+        Val::Num(n) => to_offset_date_time(&crate::exact_num(n)),
+        Val::Int(i) => OffsetDateTime::from_unix_timestamp(*i as i64).map_err(oor),
+        Val::Float(f) => {
+            let ns = (f * 1_000_000_000_f64).round() as i128;
+            OffsetDateTime::from_unix_timestamp_nanos(ns).map_err(oor)
+        }
+        _ => Err(Error::Custom(format!("{v} is not a timestamp"))),
+    }
+}
+
+/// Convert an `OffsetDateTime` to a jq-style broken-down time array.
+fn to_broken_down(dt: &time::OffsetDateTime) -> Val {
+    let sec = if dt.nanosecond() == 0 {
+        Val::Int(dt.second() as isize)
+    } else {
+        Val::Float(dt.second() as f64 + dt.nanosecond() as f64 * 1e-9_f64)
+    };
+    Val::arr(Vec::from([
+        sec,
+        Val::Int(dt.minute() as isize),
+        Val::Int(dt.hour() as isize),
+        Val::Int(dt.day() as isize),
+        Val::Int(dt.month() as u8 as isize - 1),
+        Val::Int(dt.year() as isize - 1900),
+        Val::Int(dt.weekday().number_days_from_sunday() as isize),
+        Val::Int(dt.ordinal() as isize - 1),
+    ]))
+}
+
+/// Read a jq broken-down time array back into its `isize` fields.
+fn from_broken_down(v: &Val) -> Result<[isize; BROKEN_DOWN_LEN], Error> {
+    let arr = v.clone().into_arr()?;
+    if arr.len() < BROKEN_DOWN_LEN {
+        return Err(Error::Custom(format!(
+            "broken-down time array must have at least {BROKEN_DOWN_LEN} elements, got {}",
+            arr.len()
+        )));
+    }
+    let mut out = [0isize; BROKEN_DOWN_LEN];
+    for (o, x) in out.iter_mut().zip(arr.iter()) {
+        *o = x.as_int()?;
+    }
+    Ok(out)
+}
+
+/// `gmtime`: convert a UNIX timestamp to a broken-down time array (in UTC).
+pub fn gmtime(v: &Val) -> ValR {
+    Ok(to_broken_down(&to_offset_date_time(v)?))
+}
+
+/// `localtime`: like `gmtime`, but expressed in the system's local UTC offset,
+/// falling back to UTC if the local offset cannot be determined.
+pub fn localtime(v: &Val) -> ValR {
+    use time::UtcOffset;
+    let dt = to_offset_date_time(v)?;
+    let offset = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
+    Ok(to_broken_down(&dt.to_offset(offset)))
+}
+
+/// `mktime`: convert a broken-down time array back to a UNIX timestamp.
+pub fn mktime(v: &Val) -> ValR {
+    use time::{Date, Month, PrimitiveDateTime, Time};
+    let a = from_broken_down(v)?;
+    let year = a[5] + 1900;
+    let month = Month::try_from((a[4] + 1) as u8)
+        .map_err(|e| Error::Custom(format!("invalid month in broken-down time: {e}")))?;
+    let date = Date::from_calendar_date(year as i32, month, a[3] as u8)
+        .map_err(|e| Error::Custom(format!("invalid date in broken-down time: {e}")))?;
+    let time = Time::from_hms(a[2] as u8, a[1] as u8, a[0] as u8)
+        .map_err(|e| Error::Custom(format!("invalid time in broken-down time: {e}")))?;
+    let dt = PrimitiveDateTime::new(date, time).assume_utc();
+    isize::try_from(dt.unix_timestamp())
+        .map(Val::Int)
+        .map_err(|e| Error::Custom(format!("mktime result out of range: {e}")))
+}
+
+fn weekday_abbr(w: time::Weekday) -> &'static str {
+    use time::Weekday::*;
+    match w {
+        Monday => "Mon",
+        Tuesday => "Tue",
+        Wednesday => "Wed",
+        Thursday => "Thu",
+        Friday => "Fri",
+        Saturday => "Sat",
+        Sunday => "Sun",
+    }
+}
+
+fn weekday_full(w: time::Weekday) -> &'static str {
+    use time::Weekday::*;
+    match w {
+        Monday => "Monday",
+        Tuesday => "Tuesday",
+        Wednesday => "Wednesday",
+        Thursday => "Thursday",
+        Friday => "Friday",
+        Saturday => "Saturday",
+        Sunday => "Sunday",
+    }
+}
+
+fn month_abbr(m: time::Month) -> &'static str {
+    use time::Month::*;
+    match m {
+        January => "Jan",
+        February => "Feb",
+        March => "Mar",
+        April => "Apr",
+        May => "May",
+        June => "Jun",
+        July => "Jul",
+        August => "Aug",
+        September => "Sep",
+        October => "Oct",
+        November => "Nov",
+        December => "Dec",
+    }
+}
+
+fn month_full(m: time::Month) -> &'static str {
+    use time::Month::*;
+    match m {
+        January => "January",
+        February => "February",
+        March => "March",
+        April => "April",
+        May => "May",
+        June => "June",
+        July => "July",
+        August => "August",
+        September => "September",
+        October => "October",
+        November => "November",
+        December => "December",
+    }
+}
+
+/// Translate the subset of C `strftime` conversion specifiers jq cares about
+/// into formatted text for a single `OffsetDateTime`.
+fn format_strftime(dt: &time::OffsetDateTime, fmt: &str) -> Result<String, Error> {
+    let mut out = String::new();
+    let mut chars = fmt.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => out.push_str(&dt.year().to_string()),
+            Some('y') => out.push_str(&format!("{:02}", dt.year().rem_euclid(100))),
+            Some('m') => out.push_str(&format!("{:02}", dt.month() as u8)),
+            Some('d') => out.push_str(&format!("{:02}", dt.day())),
+            Some('e') => out.push_str(&format!("{:2}", dt.day())),
+            Some('H') => out.push_str(&format!("{:02}", dt.hour())),
+            Some('M') => out.push_str(&format!("{:02}", dt.minute())),
+            Some('S') => out.push_str(&format!("{:02}", dt.second())),
+            Some('j') => out.push_str(&format!("{:03}", dt.ordinal())),
+            Some('w') => out.push_str(&dt.weekday().number_days_from_sunday().to_string()),
+            Some('a') => out.push_str(weekday_abbr(dt.weekday())),
+            Some('A') => out.push_str(weekday_full(dt.weekday())),
+            Some('b') | Some('h') => out.push_str(month_abbr(dt.month())),
+            Some('B') => out.push_str(month_full(dt.month())),
+            Some('Z') => out.push_str("UTC"),
+            Some('%') => out.push('%'),
+            Some(other) => {
+                return Err(Error::Custom(format!(
+                    "unsupported strftime conversion specifier %{other}"
+                )))
+            }
+            None => return Err(Error::Custom("dangling % at end of strftime format".into())),
+        }
+    }
+    Ok(out)
+}
+
+/// `strftime`: format a broken-down time array (or timestamp) as a string.
+pub fn strftime(v: &Val, fmt: &str) -> ValR {
+    let dt = match v {
+        Val::Arr(_) => {
+            let a = from_broken_down(v)?;
+            broken_down_to_offset_date_time(a)?
+        }
+        _ => to_offset_date_time(v)?,
+    };
+    Ok(Val::str(format_strftime(&dt, fmt)?))
+}
+
+fn broken_down_to_offset_date_time(a: [isize; BROKEN_DOWN_LEN]) -> Result<time::OffsetDateTime, Error> {
+    let arr = Val::arr(a.iter().map(|i| Val::Int(*i)).collect());
+    mktime(&arr).and_then(|t| to_offset_date_time(&t))
+}
+
+/// A single parsed field while matching a `strptime` format against its input.
+enum Spec {
+    /// A run of decimal digits, stored into the named field.
+    Numeric(NumField),
+    /// A month name (`%b`, `%h`, or `%B`): sets the month.
+    MonthName,
+    /// A weekday name (`%a` or `%A`): informational only, since the weekday
+    /// is always recomputed from the date.
+    WeekdayName,
+    /// A timezone name (`%Z`): we only ever emit/accept `UTC`.
+    Tz,
+}
+
+enum NumField {
+    Year,
+    Month,
+    Day,
+    Hour,
+    Min,
+    Sec,
+    Yday,
+    /// Day of week (`%w`): informational only, like `WeekdayName`.
+    Wday,
+}
+
+fn take_digits(s: &str) -> Result<(isize, &str), Error> {
+    // `%e` emits a space-padded day (e.g. `" 5"`), so a leading space here is
+    // not a format mismatch but the padding of a single-digit `%e` field.
+    let s = s.strip_prefix(' ').unwrap_or(s);
+    let digits: String = s.chars().take_while(char::is_ascii_digit).collect();
+    if digits.is_empty() {
+        return Err(Error::Custom(format!("expected a number in {s}")));
+    }
+    let n: isize = digits
+        .parse()
+        .map_err(|e| Error::Custom(format!("invalid number {digits}: {e}")))?;
+    Ok((n, &s[digits.len()..]))
+}
+
+/// Match a month name (full before abbreviated, since the abbreviation is a
+/// prefix of the full name) at the start of `s`, returning the month number
+/// (`1..=12`) and the unconsumed remainder.
+fn take_month_name(s: &str) -> Result<(isize, &str), Error> {
+    for m in 1u8..=12 {
+        let month = time::Month::try_from(m).unwrap();
+        for name in [month_full(month), month_abbr(month)] {
+            if let Some(rest) = s.strip_prefix(name) {
+                return Ok((m as isize, rest));
+            }
+        }
+    }
+    Err(Error::Custom(format!("expected a month name in {s}")))
+}
+
+/// Match a weekday name at the start of `s`, returning the unconsumed remainder.
+fn take_weekday_name(s: &str) -> Result<&str, Error> {
+    use time::Weekday::*;
+    for w in [Monday, Tuesday, Wednesday, Thursday, Friday, Saturday, Sunday] {
+        for name in [weekday_full(w), weekday_abbr(w)] {
+            if let Some(rest) = s.strip_prefix(name) {
+                return Ok(rest);
+            }
+        }
+    }
+    Err(Error::Custom(format!("expected a weekday name in {s}")))
+}
+
+/// `strptime`: parse a string into a broken-down time array using a
+/// `strftime`-style format string. Supports the same specifiers `strftime`
+/// emits (`%Y %y %m %d %e %H %M %S %j %w %a %A %b %h %B %Z %%`); any other
+/// specifier, or a specifier whose text doesn't match the input, is an error.
+pub fn strptime(s: &str, fmt: &str) -> ValR {
+    let mut year = 1900isize;
+    let mut month = 1isize;
+    let mut day = 1isize;
+    let mut hour = 0isize;
+    let mut min = 0isize;
+    let mut sec = 0isize;
+    let mut yday: Option<isize> = None;
+
+    let mut s = s;
+    let mut fmt_chars = fmt.chars();
+    while let Some(fc) = fmt_chars.next() {
+        if fc != '%' {
+            s = s
+                .strip_prefix(fc)
+                .ok_or_else(|| Error::Custom(format!("{s} does not match format {fmt}")))?;
+            continue;
+        }
+        let spec = match fmt_chars.next() {
+            Some('Y') => Some(Spec::Numeric(NumField::Year)),
+            Some('y') => Some(Spec::Numeric(NumField::Year)),
+            Some('m') => Some(Spec::Numeric(NumField::Month)),
+            Some('d') | Some('e') => Some(Spec::Numeric(NumField::Day)),
+            Some('H') => Some(Spec::Numeric(NumField::Hour)),
+            Some('M') => Some(Spec::Numeric(NumField::Min)),
+            Some('S') => Some(Spec::Numeric(NumField::Sec)),
+            Some('j') => Some(Spec::Numeric(NumField::Yday)),
+            Some('w') => Some(Spec::Numeric(NumField::Wday)),
+            Some('a') | Some('A') => Some(Spec::WeekdayName),
+            Some('b') | Some('h') | Some('B') => Some(Spec::MonthName),
+            Some('Z') => Some(Spec::Tz),
+            Some('%') => {
+                s = s
+                    .strip_prefix('%')
+                    .ok_or_else(|| Error::Custom(format!("{s} does not match format {fmt}")))?;
+                None
+            }
+            Some(other) => {
+                return Err(Error::Custom(format!(
+                    "unsupported strptime conversion specifier %{other}"
+                )))
+            }
+            None => return Err(Error::Custom("dangling % at end of strptime format".into())),
+        };
+        let Some(spec) = spec else { continue };
+
+        match spec {
+            Spec::Numeric(field) => {
+                let (n, rest) = take_digits(s)?;
+                s = rest;
+                match field {
+                    NumField::Year => year = if n < 100 { n + 1900 } else { n },
+                    NumField::Month => month = n,
+                    NumField::Day => day = n,
+                    NumField::Hour => hour = n,
+                    NumField::Min => min = n,
+                    NumField::Sec => sec = n,
+                    // `%j` is 1-based, the broken-down `yday` is 0-based
+                    NumField::Yday => yday = Some(n - 1),
+                    NumField::Wday => {}
+                }
+            }
+            Spec::MonthName => {
+                let (m, rest) = take_month_name(s)?;
+                month = m;
+                s = rest;
+            }
+            Spec::WeekdayName => s = take_weekday_name(s)?,
+            Spec::Tz => {
+                s = s
+                    .strip_prefix("UTC")
+                    .ok_or_else(|| Error::Custom(format!("expected a timezone name in {s}")))?;
+            }
+        }
+    }
+
+    let dt = match yday {
+        Some(yday) => {
+            let date = time::Date::from_ordinal_date(year as i32, (yday + 1) as u16)
+                .map_err(|e| Error::Custom(format!("invalid day of year in broken-down time: {e}")))?;
+            let time = time::Time::from_hms(hour as u8, min as u8, sec as u8)
+                .map_err(|e| Error::Custom(format!("invalid time in broken-down time: {e}")))?;
+            time::PrimitiveDateTime::new(date, time).assume_utc()
+        }
+        None => broken_down_to_offset_date_time([sec, min, hour, day, month - 1, year - 1900, 0, 0])?,
+    };
+    Ok(to_broken_down(&dt))
+}
+
+/// `dateadd(unit; amount)`: add `amount` units (`"seconds"`, `"minutes"`,
+/// `"hours"`, or `"days"`) to a UNIX timestamp.
+pub fn dateadd(v: &Val, unit: &str, amount: f64) -> ValR {
+    let secs_per_unit = match unit {
+        "seconds" => 1.0,
+        "minutes" => 60.0,
+        "hours" => 3600.0,
+        "days" => 86400.0,
+        _ => return Err(Error::Custom(format!("unknown dateadd unit {unit}"))),
+    };
+    let base = match v {
+        Val::Int(i) => *i as f64,
+        Val::Float(f) => *f,
+        Val::Num(n) => match crate::exact_num(n) {
+            Val::Int(i) => i as f64,
+            Val::Float(f) => f,
+            _ => return Err(Error::Custom(format!("{v} is not a timestamp"))),
+        },
+        _ => return Err(Error::Custom(format!("{v} is not a timestamp"))),
+    };
+    Ok(Val::Float(base + amount * secs_per_unit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mktime_gmtime_round_trip() {
+        let broken_down = Val::arr(Vec::from([
+            Val::Int(45),
+            Val::Int(30),
+            Val::Int(12),
+            Val::Int(15),
+            Val::Int(5), // June, 0-based
+            Val::Int(124),
+            Val::Int(0),
+            Val::Int(0),
+        ]));
+        let ts = mktime(&broken_down).unwrap();
+        let back = gmtime(&ts).unwrap();
+        let back = back.into_arr().unwrap();
+        assert_eq!(back[0], Val::Int(45));
+        assert_eq!(back[1], Val::Int(30));
+        assert_eq!(back[2], Val::Int(12));
+        assert_eq!(back[3], Val::Int(15));
+        assert_eq!(back[4], Val::Int(5));
+        assert_eq!(back[5], Val::Int(124));
+    }
+
+    #[test]
+    fn strftime_strptime_round_trip_full_specifier_set() {
+        let ts = Val::Int(1_718_452_245); // 2024-06-15T11:50:45Z, a Saturday
+        let fmt = "%Y-%m-%d %e %H:%M:%S %j %w %a %A %b %h %B %Z";
+        let formatted = strftime(&ts, fmt).unwrap();
+        let formatted = formatted.as_str().unwrap().to_string();
+        let parsed = strptime(&formatted, fmt).unwrap();
+        let original = gmtime(&ts).unwrap();
+        assert_eq!(parsed, original);
+    }
+
+    #[test]
+    fn strftime_strptime_round_trip_single_digit_e_day() {
+        // %e space-pads single-digit days (e.g. " 5"); take_digits must skip
+        // that padding rather than treating it as a format mismatch.
+        let ts = Val::Int(1_717_588_245); // 2024-06-05T11:50:45Z
+        let fmt = "%Y-%m-%e %H:%M:%S";
+        let formatted = strftime(&ts, fmt).unwrap();
+        let formatted = formatted.as_str().unwrap().to_string();
+        let parsed = strptime(&formatted, fmt).unwrap();
+        let original = gmtime(&ts).unwrap();
+        assert_eq!(parsed, original);
+    }
+
+    #[test]
+    fn strptime_rejects_unknown_weekday_name() {
+        assert!(strptime("Blursday", "%A").is_err());
+        assert!(strptime("Monday", "%A").is_ok());
+        assert!(strptime("Mon", "%a").is_ok());
+    }
+}